@@ -7,49 +7,1042 @@
 
 //! Helper to process PSBTSent/PSBTReceived events.
 
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
 
+use bitcoin::consensus::encode;
+use bitcoin::hashes::Hash;
 use bitcoin::psbt::{Input, Output};
-use bitcoin::{Amount, Psbt, TxIn, TxOut};
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::{Amount, OutPoint, Psbt, ScriptBuf, Transaction, Txid, TxIn, TxOut, Witness};
+use lightning::chain::chaininterface::BroadcasterInterface;
 use lightning::events::Event as LdkEvent;
+use lightning::ln::types::ChannelId;
+use lightning::util::persist::KVStore;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, RngCore};
 
-use crate::config::Config;
-use crate::types::{ChannelManager, Wallet};
+use crate::event::{Event, EventQueue};
+use crate::types::{Broadcaster, ChannelManager, DynStore, Wallet};
 
-pub(crate) fn process_batch_events(
-	event: LdkEvent, config: &Arc<Config>, channel_manager: &Arc<ChannelManager>,
-	wallet: &Arc<Wallet>,
+/// Errors that can occur while combining or finalizing batch PSBTs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchError {
+	/// No PSBTs were given to combine.
+	NoPsbtsToCombine,
+	/// The `unsigned_tx` fields of the PSBTs being combined don't match.
+	UnsignedTxMismatch,
+	/// Two PSBTs being combined disagree on the value stored under the same key.
+	ConflictingPsbtData,
+	/// An input couldn't be finalized, e.g. because it lacks the signatures its
+	/// `witness_script` requires.
+	PsbtFinalizationFailed,
+	/// The finalized PSBT couldn't be extracted into a network transaction.
+	PsbtExtractionFailed,
+	/// There's no peer left to route the batch PSBT to.
+	NoEligiblePeer,
+	/// The wallet couldn't contribute inputs to a new batch round.
+	InsufficientFunds,
+}
+
+impl fmt::Display for BatchError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::NoPsbtsToCombine => write!(f, "no PSBTs were given to combine"),
+			Self::UnsignedTxMismatch => {
+				write!(f, "PSBTs being combined don't share the same unsigned transaction")
+			},
+			Self::ConflictingPsbtData => {
+				write!(f, "PSBTs being combined disagree on the value stored under the same key")
+			},
+			Self::PsbtFinalizationFailed => {
+				write!(f, "failed to finalize one or more PSBT inputs")
+			},
+			Self::PsbtExtractionFailed => {
+				write!(f, "failed to extract a transaction from the finalized PSBT")
+			},
+			Self::NoEligiblePeer => {
+				write!(f, "no peer left to route the batch PSBT to")
+			},
+			Self::InsufficientFunds => {
+				write!(f, "the wallet couldn't contribute inputs to a new batch round")
+			},
+		}
+	}
+}
+
+impl std::error::Error for BatchError {}
+
+impl Wallet {
+	/// Combines multiple independently-signed copies of the same unsigned PSBT into one, per
+	/// the BIP174 Combiner role.
+	///
+	/// Every copy must carry a byte-identical `unsigned_tx`. The per-input and per-output
+	/// key-value maps are then unioned, keeping a single value per key and erroring if two
+	/// copies disagree on the value stored under the same key (e.g. two different
+	/// `partial_sigs` entries for the same public key). This lets participants sign their own
+	/// inputs in parallel rather than passing a single PSBT hop to hop.
+	pub fn combine_psbts(psbts: &[Psbt]) -> Result<Psbt, BatchError> {
+		let mut iter = psbts.iter();
+		let mut combined = iter.next().cloned().ok_or(BatchError::NoPsbtsToCombine)?;
+
+		for psbt in iter {
+			if psbt.unsigned_tx != combined.unsigned_tx {
+				return Err(BatchError::UnsignedTxMismatch);
+			}
+
+			for (dst, src) in combined.inputs.iter_mut().zip(psbt.inputs.iter()) {
+				merge_input(dst, src)?;
+			}
+			for (dst, src) in combined.outputs.iter_mut().zip(psbt.outputs.iter()) {
+				merge_output(dst, src)?;
+			}
+		}
+
+		Ok(combined)
+	}
+}
+
+fn merge_map<K: Ord + Clone, V: PartialEq + Clone>(
+	dst: &mut BTreeMap<K, V>, src: &BTreeMap<K, V>,
+) -> Result<(), BatchError> {
+	for (key, value) in src {
+		match dst.get(key) {
+			Some(existing) if existing != value => return Err(BatchError::ConflictingPsbtData),
+			Some(_) => {},
+			None => {
+				dst.insert(key.clone(), value.clone());
+			},
+		}
+	}
+	Ok(())
+}
+
+fn merge_option<T: PartialEq + Clone>(
+	dst: &mut Option<T>, src: &Option<T>,
+) -> Result<(), BatchError> {
+	match (dst.as_ref(), src) {
+		(Some(existing), Some(value)) if existing != value => {
+			return Err(BatchError::ConflictingPsbtData);
+		},
+		(None, Some(value)) => *dst = Some(value.clone()),
+		_ => {},
+	}
+	Ok(())
+}
+
+fn merge_input(dst: &mut Input, src: &Input) -> Result<(), BatchError> {
+	merge_option(&mut dst.non_witness_utxo, &src.non_witness_utxo)?;
+	merge_option(&mut dst.witness_utxo, &src.witness_utxo)?;
+	merge_map(&mut dst.partial_sigs, &src.partial_sigs)?;
+	merge_option(&mut dst.sighash_type, &src.sighash_type)?;
+	merge_option(&mut dst.redeem_script, &src.redeem_script)?;
+	merge_option(&mut dst.witness_script, &src.witness_script)?;
+	merge_map(&mut dst.bip32_derivation, &src.bip32_derivation)?;
+	merge_option(&mut dst.final_script_sig, &src.final_script_sig)?;
+	merge_option(&mut dst.final_script_witness, &src.final_script_witness)?;
+	merge_map(&mut dst.ripemd160_preimages, &src.ripemd160_preimages)?;
+	merge_map(&mut dst.sha256_preimages, &src.sha256_preimages)?;
+	merge_map(&mut dst.hash160_preimages, &src.hash160_preimages)?;
+	merge_map(&mut dst.hash256_preimages, &src.hash256_preimages)?;
+	merge_option(&mut dst.tap_key_sig, &src.tap_key_sig)?;
+	merge_map(&mut dst.tap_script_sigs, &src.tap_script_sigs)?;
+	merge_map(&mut dst.tap_scripts, &src.tap_scripts)?;
+	merge_map(&mut dst.tap_key_origins, &src.tap_key_origins)?;
+	merge_option(&mut dst.tap_internal_key, &src.tap_internal_key)?;
+	merge_option(&mut dst.tap_merkle_root, &src.tap_merkle_root)?;
+	merge_map(&mut dst.proprietary, &src.proprietary)?;
+	merge_map(&mut dst.unknown, &src.unknown)?;
+	Ok(())
+}
+
+fn merge_output(dst: &mut Output, src: &Output) -> Result<(), BatchError> {
+	merge_option(&mut dst.redeem_script, &src.redeem_script)?;
+	merge_option(&mut dst.witness_script, &src.witness_script)?;
+	merge_map(&mut dst.bip32_derivation, &src.bip32_derivation)?;
+	merge_option(&mut dst.tap_internal_key, &src.tap_internal_key)?;
+	merge_option(&mut dst.tap_tree, &src.tap_tree)?;
+	merge_map(&mut dst.tap_key_origins, &src.tap_key_origins)?;
+	merge_map(&mut dst.proprietary, &src.proprietary)?;
+	merge_map(&mut dst.unknown, &src.unknown)?;
+	Ok(())
+}
+
+/// Proprietary key prefix namespacing this crate's custom global PSBT fields (BIP174's
+/// `proprietary` map), used to smuggle data that needs to survive every hop's `psbt_hex`
+/// round-trip but isn't part of the transaction itself.
+const PROPRIETARY_PREFIX: &[u8] = b"ldk-node";
+
+/// Subtype of the proprietary key stashing a round's stable id; see [`stash_new_round_id`].
+const ROUND_ID_SUBTYPE: u8 = 0;
+
+/// Subtype of the proprietary key stashing a registered splice target; see
+/// [`register_splice_target`].
+const SPLICE_TARGET_SUBTYPE: u8 = 1;
+
+fn proprietary_key(subtype: u8) -> bitcoin::psbt::raw::ProprietaryKey {
+	bitcoin::psbt::raw::ProprietaryKey { prefix: PROPRIETARY_PREFIX.to_vec(), subtype, key: Vec::new() }
+}
+
+/// Generates a fresh random round id and stashes it in `psbt`'s global proprietary fields.
+///
+/// Unlike `psbt.unsigned_tx.compute_txid()` — which changes every time a participant joins or
+/// the pre-signing shuffle reorders inputs/outputs — mutating a PSBT's inputs/outputs never
+/// touches its global fields, so this id travels unchanged through every hop's `psbt_hex`
+/// payload. It's therefore the only handle stable enough for [`Node::batch_status`] to track a
+/// round across its whole lifecycle; see [`round_id_of`].
+///
+/// [`Node::batch_status`]: crate::Node::batch_status
+fn stash_new_round_id(psbt: &mut Psbt) -> Txid {
+	let mut bytes = [0u8; 32];
+	thread_rng().fill_bytes(&mut bytes);
+	let round_id = Txid::from_byte_array(bytes);
+	psbt.proprietary.insert(proprietary_key(ROUND_ID_SUBTYPE), round_id.to_byte_array().to_vec());
+	round_id
+}
+
+/// Reads back the round id stashed by [`stash_new_round_id`], falling back to the current
+/// `unsigned_tx`'s txid for a PSBT that doesn't carry one (e.g. one predating this mechanism).
+fn round_id_of(psbt: &Psbt) -> Txid {
+	psbt.proprietary
+		.get(&proprietary_key(ROUND_ID_SUBTYPE))
+		.and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok())
+		.map(Txid::from_byte_array)
+		.unwrap_or_else(|| psbt.unsigned_tx.compute_txid())
+}
+
+/// Where the channel that a batch's assembled funds should splice into lives, and which output
+/// of the finished transaction funds it.
+#[derive(Debug, Clone)]
+pub(crate) struct SpliceTarget {
+	pub(crate) channel_id: ChannelId,
+	pub(crate) counterparty_node_id: PublicKey,
+	pub(crate) funding_output_index: u16,
+}
+
+fn serialize_splice_target(target: &SpliceTarget) -> Vec<u8> {
+	let mut bytes = Vec::with_capacity(32 + 33 + 2);
+	bytes.extend_from_slice(&target.channel_id.0);
+	bytes.extend_from_slice(&target.counterparty_node_id.serialize());
+	bytes.extend_from_slice(&target.funding_output_index.to_be_bytes());
+	bytes
+}
+
+fn deserialize_splice_target(bytes: &[u8]) -> Option<SpliceTarget> {
+	if bytes.len() != 32 + 33 + 2 {
+		return None;
+	}
+	let mut channel_id = [0u8; 32];
+	channel_id.copy_from_slice(&bytes[0..32]);
+	let counterparty_node_id = PublicKey::from_slice(&bytes[32..65]).ok()?;
+	let funding_output_index = u16::from_be_bytes([bytes[65], bytes[66]]);
+	Some(SpliceTarget { channel_id: ChannelId(channel_id), counterparty_node_id, funding_output_index })
+}
+
+/// Marks the batch round building `psbt` as funding a channel splice-in rather than a
+/// standalone transaction: once the round's PSBT is fully signed and combined, its extracted
+/// transaction drives a `splice`/`splice_ack`/`splice_locked` negotiation (preceded by `stfu`
+/// quiescence) with `target.counterparty_node_id` instead of being broadcast on its own.
+///
+/// The target is stashed in `psbt`'s global proprietary fields (the same mechanism
+/// [`stash_new_round_id`] uses) rather than kept in this node's own state, since whichever node
+/// ends up finalizing the round — the one that happens to observe the last participant join and
+/// fans the PSBT out for signing — isn't necessarily the node that called this function.
+pub(crate) fn register_splice_target(psbt: &mut Psbt, target: SpliceTarget) {
+	psbt.proprietary.insert(proprietary_key(SPLICE_TARGET_SUBTYPE), serialize_splice_target(&target));
+}
+
+/// Reads back a splice target registered via [`register_splice_target`], if any.
+fn take_splice_target(psbt: &Psbt) -> Option<SpliceTarget> {
+	psbt.proprietary
+		.get(&proprietary_key(SPLICE_TARGET_SUBTYPE))
+		.and_then(|bytes| deserialize_splice_target(bytes))
+}
+
+/// Finalizes `psbt` and drives the channel's splice-in negotiation with the pooled funding
+/// transaction, instead of broadcasting it as a standalone transaction.
+fn drive_splice(
+	channel_manager: &Arc<ChannelManager>, mut psbt: Psbt, target: SpliceTarget,
+	round_coordinator: &Arc<BatchRoundCoordinator>, event_queue: &Arc<EventQueue>,
 ) {
-	let mut alias = "NO_ALIAS".to_string();
-	if let Some(node_alias) = config.node_alias {
-		alias = node_alias.to_string();
+	let round_id = round_id_of(&psbt);
+	let txid = psbt.unsigned_tx.compute_txid();
+
+	if let Err(err) = finalize_psbt(&mut psbt) {
+		round_coordinator.set_status(round_id, BatchRoundStatus::Failed { reason: err.to_string() });
+		let _ = event_queue.add_event(Event::BatchRoundFailed {
+			round_id,
+			reason: format!("failed to finalize splice funding PSBT: {}", err),
+			non_signers: Vec::new(),
+		});
+		return;
 	}
-	match event {
-		LdkEvent::PSBTSent {
-			next_node_id,
+
+	let tx = match psbt.extract_tx() {
+		Ok(tx) => tx,
+		Err(_) => {
+			let reason = "failed to extract splice funding transaction".to_string();
+			round_coordinator.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ =
+				event_queue.add_event(Event::BatchRoundFailed { round_id, reason, non_signers: Vec::new() });
+			return;
+		},
+	};
+
+	match channel_manager.splice_channel(
+		target.channel_id,
+		target.counterparty_node_id,
+		tx,
+		target.funding_output_index,
+	) {
+		Ok(()) => {
+			round_coordinator.set_status(round_id, BatchRoundStatus::Broadcast { txid });
+			let _ = event_queue.add_event(Event::BatchCompleted { round_id, txid });
+		},
+		Err(err) => {
+			let reason = format!("failed to start channel splice: {:?}", err);
+			round_coordinator.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ =
+				event_queue.add_event(Event::BatchRoundFailed { round_id, reason, non_signers: Vec::new() });
+		},
+	}
+}
+
+/// Adds `psbt` to the set of signed copies collected for its round, and once every participant
+/// has contributed, combines the PSBT and either drives a channel splice-in (if the round was
+/// registered via [`register_splice_target`]) or finalizes and broadcasts it as a standalone
+/// transaction.
+fn try_finalize_round(
+	psbt: Psbt, max_participants: u8, channel_manager: &Arc<ChannelManager>,
+	tx_tracker: &Arc<BatchTransactionTracker>, round_coordinator: &Arc<BatchRoundCoordinator>,
+	event_queue: &Arc<EventQueue>,
+) {
+	let round_id = round_id_of(&psbt);
+
+	let copies = {
+		let mut rounds = round_coordinator.pending_combines.lock().unwrap();
+		let round = rounds.entry(round_id).or_insert_with(Vec::new);
+		round.push(psbt);
+
+		if (round.len() as u8) < max_participants {
+			return;
+		}
+
+		rounds.remove(&round_id).unwrap()
+	};
+
+	round_coordinator.finish_round(&round_id);
+
+	match Wallet::combine_psbts(&copies) {
+		Ok(combined) => {
+			let splice_target = take_splice_target(&combined);
+			if let Some(target) = splice_target {
+				return drive_splice(channel_manager, combined, target, round_coordinator, event_queue);
+			}
+
+			match tx_tracker.finalize_and_broadcast(combined) {
+				Ok(txid) => {
+					round_coordinator.set_status(round_id, BatchRoundStatus::Broadcast { txid });
+					let _ = event_queue.add_event(Event::BatchCompleted { round_id, txid });
+				},
+				Err(err) => {
+					let reason = format!("failed to finalize batch transaction: {}", err);
+					round_coordinator
+						.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+					let _ = event_queue.add_event(Event::BatchRoundFailed {
+						round_id,
+						reason,
+						non_signers: Vec::new(),
+					});
+				},
+			}
+		},
+		Err(err) => {
+			let reason = format!("failed to combine batch PSBT: {}", err);
+			round_coordinator.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ = event_queue.add_event(Event::BatchRoundFailed {
+				round_id,
+				reason,
+				non_signers: Vec::new(),
+			});
+		},
+	}
+}
+
+/// Confirmation depth at which a broadcast batch transaction is considered settled and no
+/// longer needs to be tracked for rebroadcast.
+const BATCH_TX_CONFIRMATION_DEPTH: u32 = 6;
+
+/// How often, in blocks, an unconfirmed finalized batch transaction is rebroadcast.
+const BATCH_TX_REBROADCAST_INTERVAL_BLOCKS: u32 = 1;
+
+/// Returns whether a transaction confirmed at `confirmation_height` is buried under
+/// [`BATCH_TX_CONFIRMATION_DEPTH`] confirmations as of `height`.
+fn is_buried(height: u32, confirmation_height: u32) -> bool {
+	height.saturating_sub(confirmation_height) + 1 >= BATCH_TX_CONFIRMATION_DEPTH
+}
+
+const BATCH_TXN_PERSISTENCE_PRIMARY_NAMESPACE: &str = "batch_txn";
+const BATCH_TXN_PERSISTENCE_SECONDARY_NAMESPACE: &str = "";
+
+/// Runs the BIP174 Finalizer role over every input: collapses `partial_sigs` (together with
+/// `witness_script`) into `final_script_witness`, then drops the now-superseded non-final
+/// fields, as BIP174 requires.
+///
+/// Only native P2WPKH and P2WSH multisig inputs are handled. `final_script_sig` is never set and
+/// `redeem_script` is not used, so a P2SH-wrapped input is finalized without a script sig.
+fn finalize_psbt(psbt: &mut Psbt) -> Result<(), BatchError> {
+	for input in psbt.inputs.iter_mut() {
+		if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+			continue;
+		}
+
+		if let Some(witness_script) = &input.witness_script {
+			// P2WSH multisig: only finalize if we actually collected enough signatures to meet
+			// the script's own `OP_m` threshold, then order them to match the pubkeys in the
+			// witness script, prepended with the dummy element OP_CHECKMULTISIG's off-by-one bug
+			// expects.
+			let threshold =
+				multisig_threshold(witness_script).ok_or(BatchError::PsbtFinalizationFailed)?;
+			let collected: Vec<_> = multisig_pubkeys(witness_script)
+				.into_iter()
+				.filter_map(|pubkey_bytes| {
+					input
+						.partial_sigs
+						.iter()
+						.find(|(key, _)| key.to_bytes() == pubkey_bytes)
+						.map(|(_, sig)| sig.serialize())
+				})
+				.collect();
+			if collected.len() < threshold {
+				return Err(BatchError::PsbtFinalizationFailed);
+			}
+			let mut witness = Witness::new();
+			witness.push(Vec::new());
+			for sig in collected {
+				witness.push(sig);
+			}
+			witness.push(witness_script.as_bytes());
+			input.final_script_witness = Some(witness);
+		} else if input.partial_sigs.len() == 1 {
+			// P2WPKH single-sig.
+			let (pubkey, sig) = input.partial_sigs.iter().next().unwrap();
+			let mut witness = Witness::new();
+			witness.push(sig.serialize());
+			witness.push(pubkey.inner.serialize());
+			input.final_script_witness = Some(witness);
+		} else {
+			return Err(BatchError::PsbtFinalizationFailed);
+		}
+
+		input.partial_sigs.clear();
+		input.sighash_type = None;
+		input.redeem_script = None;
+		input.witness_script = None;
+		input.bip32_derivation.clear();
+	}
+
+	Ok(())
+}
+
+/// Returns the `m` threshold of a `OP_m <pk...> OP_n OP_CHECKMULTISIG` script, i.e. the number of
+/// signatures required to satisfy it.
+fn multisig_threshold(script: &ScriptBuf) -> Option<usize> {
+	match script.instructions().next()?.ok()? {
+		bitcoin::script::Instruction::Op(op) => {
+			let opcode = op.to_u8();
+			// OP_1 (0x51) through OP_16 (0x60) push the small integers 1 through 16.
+			if (0x51..=0x60).contains(&opcode) {
+				Some((opcode - 0x50) as usize)
+			} else {
+				None
+			}
+		},
+		_ => None,
+	}
+}
+
+/// Returns the public keys referenced by a `OP_m <pk...> OP_n OP_CHECKMULTISIG` script, in
+/// script order.
+fn multisig_pubkeys(script: &ScriptBuf) -> Vec<Vec<u8>> {
+	script
+		.instructions()
+		.filter_map(|instr| instr.ok())
+		.filter_map(|instr| match instr {
+			bitcoin::script::Instruction::PushBytes(bytes)
+				if bytes.len() == 33 || bytes.len() == 65 =>
+			{
+				Some(bytes.as_bytes().to_vec())
+			},
+			_ => None,
+		})
+		.collect()
+}
+
+/// A finalized batch transaction that's been broadcast but not yet buried under
+/// [`BATCH_TX_CONFIRMATION_DEPTH`] confirmations.
+#[derive(Debug, Clone)]
+struct TrackedBatchTx {
+	tx: Transaction,
+	first_broadcast_height: u32,
+	last_rebroadcast_height: u32,
+}
+
+impl TrackedBatchTx {
+	fn txid(&self) -> Txid {
+		self.tx.compute_txid()
+	}
+
+	fn serialize(&self) -> Vec<u8> {
+		let mut buf = Vec::new();
+		buf.extend_from_slice(&self.first_broadcast_height.to_be_bytes());
+		buf.extend_from_slice(&self.last_rebroadcast_height.to_be_bytes());
+		buf.extend_from_slice(&encode::serialize(&self.tx));
+		buf
+	}
+
+	fn deserialize(buf: &[u8]) -> Option<Self> {
+		if buf.len() < 8 {
+			return None;
+		}
+		let first_broadcast_height = u32::from_be_bytes(buf[0..4].try_into().ok()?);
+		let last_rebroadcast_height = u32::from_be_bytes(buf[4..8].try_into().ok()?);
+		let tx: Transaction = encode::deserialize(&buf[8..]).ok()?;
+		Some(Self { tx, first_broadcast_height, last_rebroadcast_height })
+	}
+}
+
+/// Finalizes, extracts, broadcasts, and rebroadcasts completed batch transactions until they're
+/// buried, analogous to LDK's `OutputSweeper`. In-flight transactions are persisted so a
+/// restart resumes rebroadcasting instead of losing track of the batch.
+pub(crate) struct BatchTransactionTracker {
+	broadcaster: Arc<Broadcaster>,
+	kv_store: Arc<DynStore>,
+	current_height: Mutex<u32>,
+	pending: Mutex<HashMap<Txid, TrackedBatchTx>>,
+}
+
+impl BatchTransactionTracker {
+	pub(crate) fn new(broadcaster: Arc<Broadcaster>, kv_store: Arc<DynStore>) -> Self {
+		let pending = Self::load_persisted(&kv_store);
+		Self { broadcaster, kv_store, current_height: Mutex::new(0), pending: Mutex::new(pending) }
+	}
+
+	fn load_persisted(kv_store: &Arc<DynStore>) -> HashMap<Txid, TrackedBatchTx> {
+		let mut pending = HashMap::new();
+		let keys = kv_store
+			.list(
+				BATCH_TXN_PERSISTENCE_PRIMARY_NAMESPACE,
+				BATCH_TXN_PERSISTENCE_SECONDARY_NAMESPACE,
+			)
+			.unwrap_or_default();
+
+		for key in keys {
+			if let Ok(buf) = kv_store.read(
+				BATCH_TXN_PERSISTENCE_PRIMARY_NAMESPACE,
+				BATCH_TXN_PERSISTENCE_SECONDARY_NAMESPACE,
+				&key,
+			) {
+				if let Some(tracked) = TrackedBatchTx::deserialize(&buf) {
+					pending.insert(tracked.txid(), tracked);
+				}
+			}
+		}
+
+		pending
+	}
+
+	fn persist(&self, tracked: &TrackedBatchTx) {
+		let _ = self.kv_store.write(
+			BATCH_TXN_PERSISTENCE_PRIMARY_NAMESPACE,
+			BATCH_TXN_PERSISTENCE_SECONDARY_NAMESPACE,
+			&tracked.txid().to_string(),
+			&tracked.serialize(),
+		);
+	}
+
+	/// Finalizes `psbt` per BIP174, extracts the resulting transaction, broadcasts it, and
+	/// begins tracking it for confirmation.
+	pub(crate) fn finalize_and_broadcast(&self, mut psbt: Psbt) -> Result<Txid, BatchError> {
+		finalize_psbt(&mut psbt)?;
+		let tx = psbt.extract_tx().map_err(|_| BatchError::PsbtExtractionFailed)?;
+		let txid = tx.compute_txid();
+
+		self.broadcaster.broadcast_transactions(&[&tx]);
+
+		let current_height = *self.current_height.lock().unwrap();
+		let tracked = TrackedBatchTx {
+			tx,
+			first_broadcast_height: current_height,
+			last_rebroadcast_height: current_height,
+		};
+		self.persist(&tracked);
+		self.pending.lock().unwrap().insert(txid, tracked);
+
+		Ok(txid)
+	}
+
+	/// Called whenever the chain tip advances. Rebroadcasts any still-unconfirmed tracked
+	/// transaction, and stops tracking any that `confirmed_heights` reports as buried under
+	/// [`BATCH_TX_CONFIRMATION_DEPTH`] confirmations.
+	pub(crate) fn best_block_connected(
+		&self, height: u32, confirmed_heights: &HashMap<Txid, u32>,
+		round_coordinator: &Arc<BatchRoundCoordinator>, event_queue: &Arc<EventQueue>,
+	) {
+		*self.current_height.lock().unwrap() = height;
+
+		let mut pending = self.pending.lock().unwrap();
+		let mut buried = Vec::new();
+
+		for (txid, tracked) in pending.iter_mut() {
+			if let Some(&confirmation_height) = confirmed_heights.get(txid) {
+				if is_buried(height, confirmation_height) {
+					// Statuses are keyed by round id, so look up the round that broadcast this
+					// transaction rather than recording the status under its txid.
+					let round_id = round_coordinator
+						.statuses
+						.lock()
+						.unwrap()
+						.iter()
+						.find(|(_, status)| {
+							matches!(status, BatchRoundStatus::Broadcast { txid: broadcast_txid } if broadcast_txid == txid)
+						})
+						.map(|(round_id, _)| *round_id)
+						.unwrap_or(*txid);
+					round_coordinator.set_status(round_id, BatchRoundStatus::Confirmed { txid: *txid });
+					let _ = event_queue.add_event(Event::BatchTransactionConfirmed { txid: *txid });
+					buried.push(*txid);
+				}
+				continue;
+			}
+
+			if height.saturating_sub(tracked.last_rebroadcast_height)
+				>= BATCH_TX_REBROADCAST_INTERVAL_BLOCKS
+			{
+				self.broadcaster.broadcast_transactions(&[&tracked.tx]);
+				tracked.last_rebroadcast_height = height;
+			}
+		}
+
+		for txid in buried {
+			pending.remove(&txid);
+			let _ = self.kv_store.remove(
+				BATCH_TXN_PERSISTENCE_PRIMARY_NAMESPACE,
+				BATCH_TXN_PERSISTENCE_SECONDARY_NAMESPACE,
+				&txid.to_string(),
+				false,
+			);
+		}
+	}
+}
+
+/// How many blocks a node waits for its configured [`BatchSigner`] to resolve a signing
+/// request before aborting the round.
+const BATCH_SIGNING_TIMEOUT_BLOCKS: u32 = 12;
+
+/// A request to sign a batch PSBT's own inputs, handed to a (possibly asynchronous or remote)
+/// signer.
+#[derive(Debug, Clone)]
+pub struct BatchSigningRequest {
+	/// Identifies this request; pass it back unchanged to
+	/// [`BatchSigningCoordinator::complete_signing_request`] or
+	/// [`BatchSigningCoordinator::reject_signing_request`].
+	pub request_id: Txid,
+	/// The PSBT to sign. Only the inputs in `input_indices` need a signature.
+	pub psbt: Psbt,
+	/// Indices into `psbt.inputs` that belong to this node.
+	pub input_indices: Vec<usize>,
+}
+
+/// A signer for a node's own batch PSBT inputs.
+///
+/// Implementations may take arbitrary time to resolve a request — e.g. an HSM, an air-gapped
+/// device, or a human approving from a watch-only coordinator — since resolution happens
+/// out-of-band via [`BatchSigningCoordinator::complete_signing_request`] /
+/// [`BatchSigningCoordinator::reject_signing_request`] rather than as a return value.
+pub trait BatchSigner: Send + Sync {
+	/// Called to request a signature. `request.psbt` must eventually be resolved via the
+	/// [`BatchSigningCoordinator`] that issued it, or the round times out after
+	/// [`BATCH_SIGNING_TIMEOUT_BLOCKS`].
+	fn request_signature(&self, request: BatchSigningRequest);
+}
+
+/// Everything needed to resume routing a PSBT once its owner's inputs have been signed.
+struct PendingSigning {
+	was_fanning_out: bool,
+	hops: Vec<PublicKey>,
+	participants: Vec<PublicKey>,
+	receiver_node_id: PublicKey,
+	prev_node_id: PublicKey,
+	uniform_amount: u64,
+	fee_per_participant: u64,
+	max_participants: u8,
+	requested_at_height: u32,
+}
+
+/// Pauses batch PSBT routing while a [`BatchSigner`] signs this node's own inputs, and resumes
+/// it once the signer completes the request. A rejected or timed-out request aborts the round
+/// instead.
+pub(crate) struct BatchSigningCoordinator {
+	signer: Arc<dyn BatchSigner>,
+	pending: Mutex<HashMap<Txid, PendingSigning>>,
+	current_height: Mutex<u32>,
+	/// Outpoints each node added to a round's PSBT via `add_utxos_to_psbt`, recorded at join time
+	/// and keyed by the round's stable id (see [`round_id_of`]). Other participants' inputs are
+	/// filled in with the same `bip32_derivation` shape ours are, so this is the only reliable way
+	/// to tell which inputs in a combined PSBT are this node's own.
+	own_inputs: Mutex<HashMap<Txid, Vec<OutPoint>>>,
+}
+
+impl BatchSigningCoordinator {
+	pub(crate) fn new(signer: Arc<dyn BatchSigner>) -> Self {
+		Self {
+			signer,
+			pending: Mutex::new(HashMap::new()),
+			current_height: Mutex::new(0),
+			own_inputs: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Records that this node added `outpoints` to `round_id`'s PSBT via `add_utxos_to_psbt`, for
+	/// [`Self::begin`] to later recover as `BatchSigningRequest::input_indices`.
+	pub(crate) fn record_own_inputs(&self, round_id: Txid, outpoints: Vec<OutPoint>) {
+		self.own_inputs.lock().unwrap().entry(round_id).or_insert_with(Vec::new).extend(outpoints);
+	}
+
+	/// Requests a signature for `psbt`'s own inputs and pauses routing until the signer
+	/// resolves it.
+	#[allow(clippy::too_many_arguments)]
+	fn begin(
+		&self, psbt: Psbt, was_fanning_out: bool, hops: Vec<PublicKey>, participants: Vec<PublicKey>,
+		receiver_node_id: PublicKey, prev_node_id: PublicKey, uniform_amount: u64,
+		fee_per_participant: u64, max_participants: u8,
+	) {
+		let request_id = round_id_of(&psbt);
+		let own_outpoints = self.own_inputs.lock().unwrap().remove(&request_id).unwrap_or_default();
+		let input_indices = psbt
+			.unsigned_tx
+			.input
+			.iter()
+			.enumerate()
+			.filter(|(_, txin)| own_outpoints.contains(&txin.previous_output))
+			.map(|(idx, _)| idx)
+			.collect();
+		let requested_at_height = *self.current_height.lock().unwrap();
+
+		self.pending.lock().unwrap().insert(
+			request_id,
+			PendingSigning {
+				was_fanning_out,
+				hops,
+				participants,
+				receiver_node_id,
+				prev_node_id,
+				uniform_amount,
+				fee_per_participant,
+				max_participants,
+				requested_at_height,
+			},
+		);
+
+		self.signer.request_signature(BatchSigningRequest { request_id, psbt, input_indices });
+	}
+
+	/// Resumes routing for a PSBT whose own inputs the configured [`BatchSigner`] has finished
+	/// signing.
+	pub fn complete_signing_request(
+		&self, request_id: Txid, signed_psbt: Psbt, channel_manager: &Arc<ChannelManager>,
+		tx_tracker: &Arc<BatchTransactionTracker>, round_coordinator: &Arc<BatchRoundCoordinator>,
+		event_queue: &Arc<EventQueue>,
+	) {
+		if let Some(continuation) = self.pending.lock().unwrap().remove(&request_id) {
+			continue_after_signing(
+				signed_psbt,
+				continuation,
+				channel_manager,
+				tx_tracker,
+				round_coordinator,
+				event_queue,
+			);
+		}
+	}
+
+	/// Aborts a signing request the [`BatchSigner`] declined to fulfill.
+	pub fn reject_signing_request(
+		&self, request_id: Txid, round_coordinator: &Arc<BatchRoundCoordinator>,
+		event_queue: &Arc<EventQueue>,
+	) {
+		if self.pending.lock().unwrap().remove(&request_id).is_some() {
+			let reason = "signer rejected the batch signing request".to_string();
+			round_coordinator
+				.set_status(request_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ = event_queue.add_event(Event::BatchRoundFailed {
+				round_id: request_id,
+				reason,
+				non_signers: Vec::new(),
+			});
+		}
+	}
+
+	/// Called whenever the chain tip advances; aborts any signing request whose signer hasn't
+	/// responded within [`BATCH_SIGNING_TIMEOUT_BLOCKS`]. The PSBT is not rerouted to another
+	/// signer; the round is marked failed.
+	pub(crate) fn expire_timed_out_requests(
+		&self, height: u32, round_coordinator: &Arc<BatchRoundCoordinator>,
+		event_queue: &Arc<EventQueue>,
+	) {
+		*self.current_height.lock().unwrap() = height;
+
+		let mut pending = self.pending.lock().unwrap();
+		let timed_out: Vec<Txid> = pending
+			.iter()
+			.filter(|(_, continuation)| {
+				height.saturating_sub(continuation.requested_at_height)
+					>= BATCH_SIGNING_TIMEOUT_BLOCKS
+			})
+			.map(|(txid, _)| *txid)
+			.collect();
+
+		for request_id in timed_out {
+			pending.remove(&request_id);
+			let reason = "batch signing request timed out".to_string();
+			round_coordinator
+				.set_status(request_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ = event_queue.add_event(Event::BatchRoundFailed {
+				round_id: request_id,
+				reason,
+				non_signers: Vec::new(),
+			});
+		}
+	}
+}
+
+/// How many blocks a fanned-out signing round may run before it's aborted and its non-signers
+/// are blamed.
+const BATCH_ROUND_TIMEOUT_BLOCKS: u32 = 24;
+
+/// Tracks the participants a fanned-out signing round expects a reply from, and which of them
+/// have contributed a signature so far.
+struct BatchRound {
+	expected_signers: Vec<PublicKey>,
+	signed: Vec<PublicKey>,
+	deadline_height: u32,
+}
+
+/// The lifecycle stage of a batch coinjoin round, as reported by [`Node::batch_status`].
+///
+/// `round_id` (and the `Txid` [`batch_status`] is queried with) is the nonce [`start_batch`]
+/// stashed in the round's PSBT (see [`stash_new_round_id`]), stable across every participant
+/// join, the pre-signing shuffle, and signing — unlike the PSBT's own `unsigned_tx` id, which
+/// changes at each of those steps.
+///
+/// [`Node::batch_status`]: crate::Node::batch_status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchRoundStatus {
+	/// Still gathering participants, or not a round this node is tracking.
+	AwaitingParticipants,
+	/// All participants have joined and the round is fanned out for signing.
+	Signing,
+	/// The combined, finalized transaction has been broadcast or, for a splice-in round, handed
+	/// to the channel's splice negotiation.
+	Broadcast {
+		/// The broadcast transaction's id.
+		txid: Txid,
+	},
+	/// The broadcast transaction reached [`BATCH_TX_CONFIRMATION_DEPTH`] confirmations.
+	Confirmed {
+		/// The confirmed transaction's id.
+		txid: Txid,
+	},
+	/// The round was aborted.
+	Failed {
+		/// Why the round was aborted.
+		reason: String,
+	},
+}
+
+/// Stamps each fanned-out signing round with a deadline, tracks which participants have
+/// contributed inputs versus supplied signatures, and on timeout aborts the round cleanly,
+/// freeing any UTXOs the wallet earmarked for it and reporting which `node_id`s never signed.
+pub(crate) struct BatchRoundCoordinator {
+	rounds: Mutex<HashMap<Txid, BatchRound>>,
+	statuses: Mutex<HashMap<Txid, BatchRoundStatus>>,
+	current_height: Mutex<u32>,
+	/// PSBT copies fanned out for parallel signing that haven't all come back yet, keyed by the
+	/// round's stable id (see [`round_id_of`]).
+	pending_combines: Mutex<HashMap<Txid, Vec<Psbt>>>,
+}
+
+impl BatchRoundCoordinator {
+	pub(crate) fn new() -> Self {
+		Self {
+			rounds: Mutex::new(HashMap::new()),
+			statuses: Mutex::new(HashMap::new()),
+			current_height: Mutex::new(0),
+			pending_combines: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Starts tracking a newly fanned-out signing round, due back within
+	/// [`BATCH_ROUND_TIMEOUT_BLOCKS`].
+	fn start_round(&self, round_id: Txid, expected_signers: Vec<PublicKey>) {
+		let deadline_height = *self.current_height.lock().unwrap() + BATCH_ROUND_TIMEOUT_BLOCKS;
+		self.rounds
+			.lock()
+			.unwrap()
+			.insert(round_id, BatchRound { expected_signers, signed: Vec::new(), deadline_height });
+		self.set_status(round_id, BatchRoundStatus::Signing);
+	}
+
+	/// Records `status` as the current lifecycle stage of `round_id`, for [`Node::batch_status`]
+	/// to report.
+	///
+	/// [`Node::batch_status`]: crate::Node::batch_status
+	pub(crate) fn set_status(&self, round_id: Txid, status: BatchRoundStatus) {
+		self.statuses.lock().unwrap().insert(round_id, status);
+	}
+
+	/// Returns the last-recorded lifecycle stage of `round_id`, or
+	/// [`BatchRoundStatus::AwaitingParticipants`] if this node hasn't tracked it reaching the
+	/// signing phase yet.
+	pub(crate) fn status(&self, round_id: &Txid) -> BatchRoundStatus {
+		self.statuses
+			.lock()
+			.unwrap()
+			.get(round_id)
+			.cloned()
+			.unwrap_or(BatchRoundStatus::AwaitingParticipants)
+	}
+
+	/// Records that `signer_node_id` has contributed its signature to `round_id`.
+	fn record_signed(&self, round_id: Txid, signer_node_id: PublicKey) {
+		if let Some(round) = self.rounds.lock().unwrap().get_mut(&round_id) {
+			if !round.signed.contains(&signer_node_id) {
+				round.signed.push(signer_node_id);
+			}
+		}
+	}
+
+	/// Stops tracking a round that combined successfully.
+	fn finish_round(&self, round_id: &Txid) {
+		self.rounds.lock().unwrap().remove(round_id);
+	}
+
+	/// Called whenever the chain tip advances; aborts any round past its deadline, frees the
+	/// UTXOs this wallet earmarked for it via `add_utxos_to_psbt`, and reports which
+	/// participants never signed so callers can exclude them from future rounds.
+	pub(crate) fn expire_timed_out_rounds(
+		&self, height: u32, wallet: &Arc<Wallet>, event_queue: &Arc<EventQueue>,
+	) {
+		*self.current_height.lock().unwrap() = height;
+
+		let mut rounds = self.rounds.lock().unwrap();
+		let timed_out: Vec<Txid> = rounds
+			.iter()
+			.filter(|(_, round)| height >= round.deadline_height)
+			.map(|(round_id, _)| *round_id)
+			.collect();
+
+		for round_id in timed_out {
+			let round = match rounds.remove(&round_id) {
+				Some(round) => round,
+				None => continue,
+			};
+
+			let non_signers: Vec<PublicKey> = round
+				.expected_signers
+				.iter()
+				.filter(|node_id| !round.signed.contains(node_id))
+				.cloned()
+				.collect();
+
+			if let Some(copies) = self.pending_combines.lock().unwrap().remove(&round_id) {
+				if let Some(psbt) = copies.first() {
+					let outpoints: Vec<_> =
+						psbt.unsigned_tx.input.iter().map(|txin| txin.previous_output).collect();
+					wallet.release_reserved_utxos(&outpoints);
+				}
+			}
+
+			let reason = format!(
+				"round timed out ({} of {} signed)",
+				round.signed.len(),
+				round.expected_signers.len()
+			);
+			self.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+			let _ = event_queue.add_event(Event::BatchRoundFailed {
+				round_id,
+				reason,
+				non_signers,
+			});
+		}
+	}
+}
+
+/// Continues routing a PSBT once its owner's own inputs have been signed: fans it out to the
+/// remaining signers, replies to whoever sent it to us, or finalizes the round.
+fn continue_after_signing(
+	psbt: Psbt, continuation: PendingSigning, channel_manager: &Arc<ChannelManager>,
+	tx_tracker: &Arc<BatchTransactionTracker>, round_coordinator: &Arc<BatchRoundCoordinator>,
+	event_queue: &Arc<EventQueue>,
+) {
+	let PendingSigning {
+		was_fanning_out,
+		mut hops,
+		mut participants,
+		receiver_node_id,
+		prev_node_id,
+		uniform_amount,
+		fee_per_participant,
+		max_participants,
+		..
+	} = continuation;
+
+	participants.retain(|key| *key != receiver_node_id);
+
+	if was_fanning_out {
+		let signers = std::mem::take(&mut hops);
+		let psbt_hex = psbt.serialize_hex();
+		let round_id = round_id_of(&psbt);
+		round_coordinator.start_round(round_id, signers.clone());
+		round_coordinator.record_signed(round_id, receiver_node_id);
+
+		try_finalize_round(psbt, max_participants, channel_manager, tx_tracker, round_coordinator, event_queue);
+
+		for signer_node_id in signers {
+			let _ = channel_manager.send_psbt(
+				signer_node_id,
+				uniform_amount,
+				fee_per_participant,
+				max_participants,
+				participants.clone(),
+				Vec::new(),
+				psbt_hex.clone(),
+				true,
+			);
+		}
+	} else {
+		let _ = channel_manager.send_psbt(
+			prev_node_id,
 			uniform_amount,
 			fee_per_participant,
 			max_participants,
 			participants,
-			hops,
-			psbt_hex,
-			sign,
-		} => {
-			println!(
-        "[{}] PSBTSent    : next_node={} | uni_amount={} | fee={} | max_p={} | participants={} | hops={} | len={} | sign={}",
-        alias,
-        next_node_id,
-        uniform_amount,
-        fee_per_participant,
-        max_participants,
-        participants.len(),
-        hops.len(),
-        psbt_hex.len(),
-        sign,
-      );
-		},
+			Vec::new(),
+			psbt.serialize_hex(),
+			true,
+		);
+	}
+}
+
+pub(crate) fn process_batch_events(
+	event: LdkEvent, channel_manager: &Arc<ChannelManager>, wallet: &Arc<Wallet>,
+	tx_tracker: &Arc<BatchTransactionTracker>, batch_signer: &Arc<BatchSigningCoordinator>,
+	round_coordinator: &Arc<BatchRoundCoordinator>, event_queue: &Arc<EventQueue>,
+) {
+	match event {
+		LdkEvent::PSBTSent { .. } => {},
 		LdkEvent::PSBTReceived {
 			receiver_node_id,
 			prev_node_id,
@@ -61,26 +1054,29 @@ pub(crate) fn process_batch_events(
 			psbt_hex,
 			sign,
 		} => {
-			println!(
-        "[{}] PSBTReceived: prev_node={} | uni_amount={} | fee={} | max_p={} | participants={} | hops={} | len={} | sign={}",
-        alias,
-        prev_node_id,
-        uniform_amount,
-        fee_per_participant,
-        max_participants,
-        participants.len(),
-        hops.len(),
-        psbt_hex.len(),
-        sign,
-      );
-
-			let mut psbt = Psbt::deserialize(&hex::decode(psbt_hex).unwrap()).unwrap();
+			let decoded_psbt =
+				hex::decode(psbt_hex).ok().and_then(|bytes| Psbt::deserialize(&bytes).ok());
+			let mut psbt = match decoded_psbt {
+				Some(psbt) => psbt,
+				None => {
+					// A counterparty sent us malformed PSBT data; abort rather than panic. There's
+					// no well-formed transaction to key this round on, so there's nothing further
+					// to release or track.
+					let _ = event_queue.add_event(Event::BatchRoundFailed {
+						round_id: Txid::all_zeros(),
+						reason: "received malformed batch PSBT data from a peer".to_string(),
+						non_signers: Vec::new(),
+					});
+					return;
+				},
+			};
 
 			let mut hops = hops;
 			let mut participants = participants;
 
 			// Not a participant yet
 			if !sign && !participants.contains(&receiver_node_id) {
+				let round_id = round_id_of(&psbt);
 				participants.push(receiver_node_id);
 				// Add node's inputs/outputs and route it to the next node
 				let fee = Amount::from_sat(fee_per_participant);
@@ -88,15 +1084,34 @@ pub(crate) fn process_batch_events(
 				let uniform_amount_opt =
 					if uniform_amount > 0 { Some(Amount::from_sat(uniform_amount)) } else { None };
 
-				wallet.add_utxos_to_psbt(&mut psbt, 2, uniform_amount_opt, fee, false).unwrap();
+				let prior_input_count = psbt.unsigned_tx.input.len();
+				if wallet.add_utxos_to_psbt(&mut psbt, 2, uniform_amount_opt, fee, false).is_err() {
+					// We can't fund our share; abort the round rather than panic.
+					let reason = "failed to fund our share of the batch round".to_string();
+					round_coordinator
+						.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+					let _ = event_queue.add_event(Event::BatchRoundFailed {
+						round_id,
+						reason,
+						non_signers: Vec::new(),
+					});
+					return;
+				}
+
+				let own_outpoints: Vec<_> = psbt.unsigned_tx.input[prior_input_count..]
+					.iter()
+					.map(|txin| txin.previous_output)
+					.collect();
+				batch_signer.record_own_inputs(round_id, own_outpoints);
+
+				let _ = event_queue
+					.add_event(Event::BatchParticipantJoined { round_id, node_id: receiver_node_id });
 			}
 
 			let mut sign = sign;
 			if (participants.len() as u8) >= max_participants {
 				sign = true;
 
-				// Shuffling inputs/outputs
-				println!("\n[{}] PSBTReceived: Shuffling inputs/outputs before starting the Signing workflow...", alias);
 				let mut rng = thread_rng();
 				let mut paired_inputs: Vec<(Input, TxIn)> = psbt
 					.inputs
@@ -127,7 +1142,10 @@ pub(crate) fn process_batch_events(
 				psbt.outputs = shuffled_psbt_outputs;
 				psbt.unsigned_tx.output = shuffled_tx_outputs;
 
-				println!("\n[{}] PSBTReceived: Starting the Signing workflow (send final PSBT back to initial node)...\n", alias);
+				let _ = event_queue.add_event(Event::BatchSigningStarted {
+					round_id: round_id_of(&psbt),
+					participants: participants.clone(),
+				});
 			}
 
 			let open_channels = channel_manager.list_channels();
@@ -138,7 +1156,7 @@ pub(crate) fn process_batch_events(
 					if participants.contains(&channel_details.counterparty.node_id) {
 						continue;
 					}
-					if hops.last().unwrap() == &channel_details.counterparty.node_id {
+					if hops.last().map_or(false, |last| last == &channel_details.counterparty.node_id) {
 						continue;
 					}
 					next_node_id = Some(channel_details.counterparty.node_id);
@@ -159,77 +1177,337 @@ pub(crate) fn process_batch_events(
 					}
 				}
 
-				hops.push(receiver_node_id);
-				let psbt_hex = psbt.serialize_hex();
-
-				let _ = channel_manager.send_psbt(
-					next_node_id.unwrap(),
-					uniform_amount,
-					fee_per_participant,
-					max_participants,
-					participants.clone(),
-					hops.clone(),
-					psbt_hex,
-					false,
-				);
+				match next_node_id {
+					Some(next_node_id) => {
+						hops.push(receiver_node_id);
+						let psbt_hex = psbt.serialize_hex();
+
+						let _ = channel_manager.send_psbt(
+							next_node_id,
+							uniform_amount,
+							fee_per_participant,
+							max_participants,
+							participants.clone(),
+							hops.clone(),
+							psbt_hex,
+							false,
+						);
+					},
+					None => {
+						// No peer left to route the PSBT to; abort rather than panic, and free
+						// any UTXOs we'd already earmarked for this round.
+						let round_id = round_id_of(&psbt);
+						let outpoints: Vec<_> =
+							psbt.unsigned_tx.input.iter().map(|txin| txin.previous_output).collect();
+						wallet.release_reserved_utxos(&outpoints);
+						let reason = BatchError::NoEligiblePeer.to_string();
+						round_coordinator
+							.set_status(round_id, BatchRoundStatus::Failed { reason: reason.clone() });
+						let _ = event_queue.add_event(Event::BatchRoundFailed {
+							round_id,
+							reason,
+							non_signers: Vec::new(),
+						});
+					},
+				}
 			} else {
+				// This node holds the pre-signing `hops` trail (i.e. it just finished
+				// shuffling) and is the one responsible for fanning the PSBT out.
+				let was_fanning_out = !hops.is_empty();
+
 				// Check if we need to sign or just route the PSBT to someone else
 				if participants.contains(&receiver_node_id) {
-					println!("[{}] PSBTReceived: Signing...", alias);
-					wallet.payjoin_sign_psbt(&mut psbt).unwrap();
-					participants.retain(|key| *key != receiver_node_id);
-				}
-
-				let psbt_hex = psbt.serialize_hex();
+					// Signing may take arbitrary time (an HSM, an air-gapped device, a human
+					// approving on a watch-only coordinator), so hand our inputs off to the
+					// configured signer and pause routing this PSBT until it resolves.
+					batch_signer.begin(
+						psbt,
+						was_fanning_out,
+						hops,
+						participants,
+						receiver_node_id,
+						prev_node_id,
+						uniform_amount,
+						fee_per_participant,
+						max_participants,
+					);
+				} else if was_fanning_out {
+					// Broadcast the shuffled PSBT to every remaining signer at once instead of
+					// walking it through them one hop at a time: each signs only its own
+					// inputs, independently of the others, and replies straight back to us.
+					let signers = std::mem::take(&mut hops);
+					let psbt_hex = psbt.serialize_hex();
+					let round_id = round_id_of(&psbt);
+					round_coordinator.start_round(round_id, signers.clone());
 
-				// Do we need more signatures?
-				if hops.len() > 0 {
-					let next_signer_node_id = hops.pop().unwrap();
-					if channel_manager.list_channels_with_counterparty(&next_signer_node_id).len()
-						> 0
-					{
+					for signer_node_id in signers {
 						let _ = channel_manager.send_psbt(
-							next_signer_node_id,
+							signer_node_id,
 							uniform_amount,
 							fee_per_participant,
 							max_participants,
-							participants,
-							hops,
-							psbt_hex,
+							participants.clone(),
+							Vec::new(),
+							psbt_hex.clone(),
 							true,
 						);
-					} else {
-						let mut inner_participants = participants.clone();
-						for node_id in participants.iter().rev() {
-							if channel_manager.list_channels_with_counterparty(&node_id).len() > 0 {
-								// We need to add back the next_signer_node_id to participants
-								if !inner_participants.contains(&next_signer_node_id) {
-									inner_participants.push(next_signer_node_id);
-								}
-								let _ = channel_manager.send_psbt(
-									node_id.clone(),
-									uniform_amount,
-									fee_per_participant,
-									max_participants,
-									inner_participants,
-									hops,
-									psbt_hex,
-									true,
-								);
-								break;
-							}
-						}
 					}
 				} else {
-					println!(
-						"[{}] PSBTReceived: PSBT was signed by all participants! (len={})",
-						alias,
-						psbt_hex.len()
+					// A signed copy has come back to us; fold it into the round.
+					round_coordinator.record_signed(round_id_of(&psbt), prev_node_id);
+					try_finalize_round(
+						psbt,
+						max_participants,
+						channel_manager,
+						tx_tracker,
+						round_coordinator,
+						event_queue,
 					);
-					wallet.push_to_batch_psbts(psbt_hex).unwrap();
 				}
 			}
 		},
 		_ => {},
 	}
 }
+
+/// Originates a new batch coinjoin round: seeds a fresh PSBT with this node's own inputs and
+/// forwards it to a peer to begin gathering the rest of the participants, exposed as
+/// `Node::start_batch`.
+///
+/// Returns the round's id, a nonce stashed in the PSBT's proprietary fields (see
+/// [`stash_new_round_id`]) that stays stable as participants join, the PSBT is shuffled, and the
+/// round signs, broadcasts, and confirms — a stable handle [`batch_status`] can be polled with
+/// for the round's whole lifecycle.
+///
+/// Pass `splice_target` to fund a channel splice-in with the pooled batch funds instead of
+/// producing a standalone transaction; see [`register_splice_target`].
+pub(crate) fn start_batch(
+	channel_manager: &Arc<ChannelManager>, wallet: &Arc<Wallet>, event_queue: &Arc<EventQueue>,
+	batch_signer: &Arc<BatchSigningCoordinator>, uniform_amount: u64, fee_per_participant: u64,
+	max_participants: u8, splice_target: Option<SpliceTarget>,
+) -> Result<Txid, BatchError> {
+	let our_node_id = channel_manager.get_our_node_id();
+
+	let next_node_id = channel_manager
+		.list_channels()
+		.first()
+		.map(|channel_details| channel_details.counterparty.node_id)
+		.ok_or(BatchError::NoEligiblePeer)?;
+
+	let skeleton = Transaction {
+		version: bitcoin::transaction::Version::TWO,
+		lock_time: bitcoin::absolute::LockTime::ZERO,
+		input: Vec::new(),
+		output: Vec::new(),
+	};
+	let mut psbt =
+		Psbt::from_unsigned_tx(skeleton).map_err(|_| BatchError::InsufficientFunds)?;
+
+	let fee = Amount::from_sat(fee_per_participant);
+	let uniform_amount_opt =
+		if uniform_amount > 0 { Some(Amount::from_sat(uniform_amount)) } else { None };
+	wallet
+		.add_utxos_to_psbt(&mut psbt, 2, uniform_amount_opt, fee, false)
+		.map_err(|_| BatchError::InsufficientFunds)?;
+
+	let round_id = stash_new_round_id(&mut psbt);
+
+	let own_outpoints: Vec<_> =
+		psbt.unsigned_tx.input.iter().map(|txin| txin.previous_output).collect();
+	batch_signer.record_own_inputs(round_id, own_outpoints);
+
+	// Mark this round as funding a channel splice-in instead of a standalone transaction. The
+	// target travels inside `psbt`'s own proprietary fields, so it reaches whichever node ends
+	// up finalizing the round (the one that happens to observe the last participant join and
+	// fans the PSBT out for signing) even when that isn't this node.
+	if let Some(target) = splice_target {
+		register_splice_target(&mut psbt, target);
+	}
+
+	let psbt_hex = psbt.serialize_hex();
+
+	let _ = event_queue
+		.add_event(Event::BatchParticipantJoined { round_id, node_id: our_node_id });
+
+	let _ = channel_manager.send_psbt(
+		next_node_id,
+		uniform_amount,
+		fee_per_participant,
+		max_participants,
+		vec![our_node_id],
+		vec![our_node_id],
+		psbt_hex,
+		false,
+	);
+
+	Ok(round_id)
+}
+
+/// Reports the current lifecycle stage of a batch coinjoin round previously started with
+/// [`start_batch`], exposed as `Node::batch_status`.
+pub(crate) fn batch_status(
+	round_coordinator: &Arc<BatchRoundCoordinator>, round_id: Txid,
+) -> BatchRoundStatus {
+	round_coordinator.status(&round_id)
+}
+
+#[cfg(test)]
+mod tests {
+	use bitcoin::blockdata::opcodes::all::{OP_CHECKMULTISIG, OP_PUSHNUM_2, OP_PUSHNUM_3};
+	use bitcoin::blockdata::script::Builder;
+	use bitcoin::ecdsa;
+	use bitcoin::secp256k1::{self, Message, Secp256k1, SecretKey};
+	use bitcoin::sighash::EcdsaSighashType;
+	use bitcoin::{OutPoint, Sequence, TxOut};
+
+	use super::*;
+
+	fn unsigned_tx() -> Transaction {
+		Transaction {
+			version: bitcoin::transaction::Version::TWO,
+			lock_time: bitcoin::absolute::LockTime::ZERO,
+			input: vec![TxIn {
+				previous_output: OutPoint::null(),
+				script_sig: ScriptBuf::new(),
+				sequence: Sequence::ZERO,
+				witness: Witness::new(),
+			}],
+			output: vec![TxOut {
+				value: Amount::from_sat(1_000),
+				script_pubkey: ScriptBuf::new(),
+			}],
+		}
+	}
+
+	/// Builds an arbitrary but well-formed (pubkey, signature) pair. `finalize_psbt` never
+	/// validates a signature against the sighash it's supposed to cover, only that one is
+	/// present for each required pubkey, so a signature over dummy data is enough to exercise it.
+	fn dummy_sig(seed: u8) -> (bitcoin::PublicKey, ecdsa::Signature) {
+		let secp = Secp256k1::new();
+		let secret_key = SecretKey::from_slice(&[seed; 32]).unwrap();
+		let public_key =
+			bitcoin::PublicKey::new(secp256k1::PublicKey::from_secret_key(&secp, &secret_key));
+		let message = Message::from_digest([seed; 32]);
+		let signature = secp.sign_ecdsa(&message, &secret_key);
+		(public_key, ecdsa::Signature { signature, sighash_type: EcdsaSighashType::All })
+	}
+
+	#[test]
+	fn combine_psbts_unions_disjoint_partial_sigs() {
+		let base = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+		let (pk1, sig1) = dummy_sig(1);
+		let (pk2, sig2) = dummy_sig(2);
+
+		let mut psbt_a = base.clone();
+		psbt_a.inputs[0].partial_sigs.insert(pk1, sig1.clone());
+		let mut psbt_b = base;
+		psbt_b.inputs[0].partial_sigs.insert(pk2, sig2.clone());
+
+		let combined = Wallet::combine_psbts(&[psbt_a, psbt_b]).unwrap();
+		assert_eq!(combined.inputs[0].partial_sigs.len(), 2);
+		assert_eq!(combined.inputs[0].partial_sigs.get(&pk1), Some(&sig1));
+		assert_eq!(combined.inputs[0].partial_sigs.get(&pk2), Some(&sig2));
+	}
+
+	#[test]
+	fn combine_psbts_rejects_conflicting_partial_sigs() {
+		let base = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+		let (pk1, sig1) = dummy_sig(1);
+		let (_, sig2) = dummy_sig(2);
+
+		let mut psbt_a = base.clone();
+		psbt_a.inputs[0].partial_sigs.insert(pk1, sig1);
+		let mut psbt_b = base;
+		psbt_b.inputs[0].partial_sigs.insert(pk1, sig2);
+
+		assert_eq!(
+			Wallet::combine_psbts(&[psbt_a, psbt_b]).unwrap_err(),
+			BatchError::ConflictingPsbtData
+		);
+	}
+
+	#[test]
+	fn combine_psbts_rejects_mismatched_unsigned_tx() {
+		let psbt_a = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+
+		let mut other_tx = unsigned_tx();
+		other_tx.output[0].value = Amount::from_sat(2_000);
+		let psbt_b = Psbt::from_unsigned_tx(other_tx).unwrap();
+
+		assert_eq!(
+			Wallet::combine_psbts(&[psbt_a, psbt_b]).unwrap_err(),
+			BatchError::UnsignedTxMismatch
+		);
+	}
+
+	#[test]
+	fn finalize_psbt_p2wpkh_single_sig() {
+		let (pk, sig) = dummy_sig(1);
+		let mut psbt = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+		psbt.inputs[0].partial_sigs.insert(pk, sig);
+
+		finalize_psbt(&mut psbt).unwrap();
+
+		let witness = psbt.inputs[0].final_script_witness.as_ref().unwrap();
+		assert_eq!(witness.len(), 2);
+		assert!(psbt.inputs[0].partial_sigs.is_empty());
+	}
+
+	#[test]
+	fn finalize_psbt_p2wsh_multisig_fully_signed() {
+		let (pk1, sig1) = dummy_sig(1);
+		let (pk2, sig2) = dummy_sig(2);
+		let (pk3, _) = dummy_sig(3);
+		let witness_script = Builder::new()
+			.push_opcode(OP_PUSHNUM_2)
+			.push_key(&pk1)
+			.push_key(&pk2)
+			.push_key(&pk3)
+			.push_opcode(OP_PUSHNUM_3)
+			.push_opcode(OP_CHECKMULTISIG)
+			.into_script();
+
+		let mut psbt = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+		psbt.inputs[0].witness_script = Some(witness_script);
+		psbt.inputs[0].partial_sigs.insert(pk1, sig1);
+		psbt.inputs[0].partial_sigs.insert(pk2, sig2);
+
+		finalize_psbt(&mut psbt).unwrap();
+
+		// Dummy element, two signatures, and the witness script itself.
+		let witness = psbt.inputs[0].final_script_witness.as_ref().unwrap();
+		assert_eq!(witness.len(), 4);
+	}
+
+	#[test]
+	fn finalize_psbt_p2wsh_multisig_under_signed() {
+		let (pk1, sig1) = dummy_sig(1);
+		let (pk2, _) = dummy_sig(2);
+		let (pk3, _) = dummy_sig(3);
+		let witness_script = Builder::new()
+			.push_opcode(OP_PUSHNUM_2)
+			.push_key(&pk1)
+			.push_key(&pk2)
+			.push_key(&pk3)
+			.push_opcode(OP_PUSHNUM_3)
+			.push_opcode(OP_CHECKMULTISIG)
+			.into_script();
+
+		let mut psbt = Psbt::from_unsigned_tx(unsigned_tx()).unwrap();
+		psbt.inputs[0].witness_script = Some(witness_script);
+		// Only one of the required two signatures is present.
+		psbt.inputs[0].partial_sigs.insert(pk1, sig1);
+
+		assert_eq!(finalize_psbt(&mut psbt), Err(BatchError::PsbtFinalizationFailed));
+	}
+
+	#[test]
+	fn is_buried_respects_confirmation_depth() {
+		// A transaction confirmed in the tip block has 1 confirmation, so it needs
+		// `BATCH_TX_CONFIRMATION_DEPTH - 1` further blocks before it's buried.
+		assert!(!is_buried(100, 100));
+		assert!(!is_buried(100 + BATCH_TX_CONFIRMATION_DEPTH - 2, 100));
+		assert!(is_buried(100 + BATCH_TX_CONFIRMATION_DEPTH - 1, 100));
+		assert!(is_buried(100 + BATCH_TX_CONFIRMATION_DEPTH, 100));
+	}
+}